@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_core::fs::paths::RelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact::fs::ExecutorFs;
+
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineContext;
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineLocation;
+
+/// The [`CommandLineContext`] used when building a command line that runs with the executor's
+/// working directory as its root: paths are rendered relative to the project root.
+pub struct DefaultCommandLineContext<'a> {
+    fs: &'a ExecutorFs<'a>,
+    next_macro_id: u64,
+}
+
+impl<'a> DefaultCommandLineContext<'a> {
+    pub fn new(fs: &'a ExecutorFs<'a>) -> Self {
+        Self {
+            fs,
+            next_macro_id: 0,
+        }
+    }
+}
+
+impl<'a> CommandLineContext for DefaultCommandLineContext<'a> {
+    fn fs(&self) -> &ExecutorFs {
+        self.fs
+    }
+
+    fn resolve_project_path(
+        &self,
+        path: ProjectRelativePathBuf,
+    ) -> anyhow::Result<CommandLineLocation> {
+        Ok(CommandLineLocation::from_relative_path(
+            RelativePathBuf::from_path(path.as_str())?,
+            self.fs,
+        ))
+    }
+
+    fn next_macro_file_path(&mut self) -> anyhow::Result<RelativePathBuf> {
+        let id = self.next_macro_id;
+        self.next_macro_id += 1;
+        Ok(RelativePathBuf::from(format!("__macros__/{id}.macro")))
+    }
+}
@@ -23,6 +23,7 @@ use thiserror::Error;
 use crate::attrs::resolve::attr_type::arg::value::ResolvedStringWithMacros;
 use crate::interpreter::rule_defs::artifact::FrozenStarlarkOutputArtifact;
 use crate::interpreter::rule_defs::artifact::StarlarkArtifact;
+use crate::interpreter::rule_defs::cmd_args::options::LineEnding;
 use crate::interpreter::rule_defs::cmd_args::options::QuoteStyle;
 use crate::interpreter::rule_defs::provider::builtin::run_info::FrozenRunInfo;
 use crate::interpreter::rule_defs::provider::builtin::run_info::RunInfo;
@@ -45,11 +46,73 @@ enum CommandLineArgError {
         "expected command line item to be a string, artifact, or label, or list thereof, not `{repr}`"
     )]
     InvalidItemType { repr: String },
+    #[error("{}", render_invalid_item_at(.path, .container, .repr))]
+    InvalidItemTypeAt {
+        path: String,
+        container: String,
+        repr: String,
+    },
+}
+
+/// Render an annotated, multi-line diagnostic for a bad command line item nested inside a larger
+/// `cmd_args(*args)` invocation. The rejected element's `repr` is underlined with carets within the
+/// rendered `container`, in the style of annotated source snippets, and prefixed with the full
+/// index path (e.g. `args[2][0]`) into the nested lists.
+fn render_invalid_item_at(path: &str, container: &str, repr: &str) -> String {
+    const HEADER: &str =
+        "expected command line item to be a string, artifact, or label, or list thereof";
+    let underline = match container.find(repr) {
+        // `find` returns a byte offset; the underline is printed in characters, so count the
+        // characters preceding the match to stay aligned for non-ASCII containers.
+        Some(offset) => {
+            let columns = container[..offset].chars().count();
+            format!(
+                "\n  {}{} not a valid command line item",
+                " ".repeat(columns),
+                "^".repeat(repr.chars().count()),
+            )
+        }
+        // The element's repr is not a verbatim substring of the container's repr (e.g. it was
+        // reformatted when the list was rendered); fall back to just naming the item.
+        None => format!("\n  (offending item: `{repr}`)"),
+    };
+    format!("{HEADER}\n\n  {path}\n  {container}{underline}")
+}
+
+/// The position of a command line item within a (possibly nested) `cmd_args(*args)` invocation,
+/// tracked as a stack of indices so that diagnostics can report the full path, e.g. `args[2][0]`.
+#[derive(Clone, Debug, Default)]
+pub struct CommandLineArgPath(Vec<usize>);
+
+impl CommandLineArgPath {
+    pub fn push(&mut self, index: usize) {
+        self.0.push(index);
+    }
+
+    pub fn pop(&mut self) {
+        self.0.pop();
+    }
+
+    fn render(&self) -> String {
+        let mut out = "args".to_owned();
+        for index in &self.0 {
+            out.push_str(&format!("[{index}]"));
+        }
+        out
+    }
 }
 
 pub trait ValueAsCommandLineLike<'v> {
     fn as_command_line(&self) -> Option<&'v dyn CommandLineArgLike>;
     fn as_command_line_err(&self) -> anyhow::Result<&'v dyn CommandLineArgLike>;
+    /// Like `as_command_line_err`, but with positional context: `path` is the index path into the
+    /// nested args/lists and `container` is the `to_repr()` of the surrounding list. The resulting
+    /// error underlines the offending element within the rendered container.
+    fn as_command_line_err_at(
+        &self,
+        path: &CommandLineArgPath,
+        container: &str,
+    ) -> anyhow::Result<&'v dyn CommandLineArgLike>;
 }
 
 pub(crate) trait ValueAsFrozenCommandLineLike {
@@ -92,6 +155,21 @@ impl<'v> ValueAsCommandLineLike<'v> for Value<'v> {
             .into()
         })
     }
+
+    fn as_command_line_err_at(
+        &self,
+        path: &CommandLineArgPath,
+        container: &str,
+    ) -> anyhow::Result<&'v dyn CommandLineArgLike> {
+        self.as_command_line().ok_or_else(|| {
+            CommandLineArgError::InvalidItemTypeAt {
+                path: path.render(),
+                container: container.to_owned(),
+                repr: self.to_value().to_repr(),
+            }
+            .into()
+        })
+    }
 }
 
 impl ValueAsFrozenCommandLineLike for FrozenValue {
@@ -132,13 +210,17 @@ pub fn register_cmd_args(builder: &mut GlobalsBuilder) {
     /// * `format` - a string that provides a format to apply to the argument. for example, `cmd_args(x, format="--args={}")` would prepend `--args=` before `x`, or if `x` was a list, before each element in `x`.
     /// * `delimiter` - added between arguments to join them together. For example, `cmd_args(["--args=",x], delimiter="")` would produce a single argument to the underlying tool.
     /// * `prepend` - added as a separate argument before each argument.
-    /// * `quote` - indicates whether quoting is to be applied to each argument. The only current valid value is `"shell"`.
+    /// * `quote` - indicates whether quoting is to be applied to each argument. The valid values are `"shell"` (POSIX-shell escaping) and `"windows"` (escaping per the `CommandLineToArgvW` rules, for tools launched via `CreateProcess`).
+    /// * `at_argfile` - spills the flattened command line into a response file and replaces it on the command line with a single `@<path>` argument, as understood by rustc and many other compilers. The value is the output artifact (or filename) the response file is written to, one argument per line. This avoids `E2BIG` failures on large linker/javac invocations without manually constructing and `write`-ing the file.
+    /// * `line_ending` - the line ending used to separate arguments in the response file produced by `at_argfile`. Valid values are `"lf"` (the default) and `"crlf"`.
     fn cmd_args<'v>(
         #[starlark(args)] args: Vec<Value<'v>>,
         delimiter: Option<StringValue<'v>>,
         format: Option<StringValue<'v>>,
         prepend: Option<StringValue<'v>>,
         quote: Option<&str>,
+        at_argfile: Option<Value<'v>>,
+        line_ending: Option<&str>,
     ) -> anyhow::Result<StarlarkCommandLine<'v>> {
         StarlarkCommandLine::try_from_values_with_options(
             &args,
@@ -146,6 +228,8 @@ pub fn register_cmd_args(builder: &mut GlobalsBuilder) {
             format,
             prepend,
             quote.try_map(QuoteStyle::parse)?,
+            at_argfile,
+            line_ending.try_map(LineEnding::parse)?,
         )
     }
 }
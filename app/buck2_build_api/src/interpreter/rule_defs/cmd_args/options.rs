@@ -0,0 +1,144 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use gazebo::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+enum CommandLineOptionsError {
+    #[error("expected `quote` to be one of `shell` or `windows`, got `{0}`")]
+    UnknownQuoteStyle(String),
+    #[error("expected `line_ending` to be one of `lf` or `crlf`, got `{0}`")]
+    UnknownLineEnding(String),
+}
+
+/// How each argument should be escaped before being placed on the command line.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// POSIX-shell escaping, suitable for arguments passed through `/bin/sh`.
+    Shell,
+    /// Escaping per the `CommandLineToArgvW` rules used by `CreateProcess` on Windows.
+    Windows,
+}
+
+impl QuoteStyle {
+    pub fn parse(s: &str) -> anyhow::Result<QuoteStyle> {
+        match s {
+            "shell" => Ok(QuoteStyle::Shell),
+            "windows" => Ok(QuoteStyle::Windows),
+            _ => Err(CommandLineOptionsError::UnknownQuoteStyle(s.to_owned()).into()),
+        }
+    }
+
+    /// Quote a single already-rendered argument according to this style.
+    pub fn quote(&self, arg: &str) -> String {
+        match self {
+            QuoteStyle::Shell => shell_quote(arg),
+            QuoteStyle::Windows => windows_quote(arg),
+        }
+    }
+}
+
+/// POSIX-shell quoting: leave simple tokens untouched, otherwise single-quote and escape any
+/// embedded single quotes as `'\''`.
+fn shell_quote(arg: &str) -> String {
+    fn is_safe(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '=' | ':' | ',' | '+' | '@')
+    }
+
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_owned();
+    }
+
+    let mut out = String::with_capacity(arg.len() + 2);
+    out.push('\'');
+    for c in arg.chars() {
+        if c == '\'' {
+            out.push_str("'\\''");
+        } else {
+            out.push(c);
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Windows quoting per the `CommandLineToArgvW` rules. Backslashes are only doubled when they
+/// immediately precede a `"` (including the synthetic closing quote); otherwise they are emitted
+/// verbatim.
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c| c == ' ' || c == '\t' || c == '"') {
+        return arg.to_owned();
+    }
+
+    let mut out = String::with_capacity(arg.len() + 2);
+    out.push('"');
+    let mut backslashes: usize = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // The accumulated backslashes precede a quote, so double them, then escape the
+                // quote itself with one more backslash.
+                for _ in 0..(backslashes * 2 + 1) {
+                    out.push('\\');
+                }
+                out.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                // Backslashes not followed by a quote are literal: emit them as-is.
+                for _ in 0..backslashes {
+                    out.push('\\');
+                }
+                backslashes = 0;
+                out.push(c);
+            }
+        }
+    }
+    // The trailing backslashes precede the closing quote, so they must be doubled.
+    for _ in 0..(backslashes * 2) {
+        out.push('\\');
+    }
+    out.push('"');
+    out
+}
+
+/// The line ending used to separate arguments written into an `at_argfile` response file. The
+/// `@path` convention tolerates both Unix and Windows endings.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix line endings (`\n`). The default.
+    Lf,
+    /// Windows line endings (`\r\n`).
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn parse(s: &str) -> anyhow::Result<LineEnding> {
+        match s {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            _ => Err(CommandLineOptionsError::UnknownLineEnding(s.to_owned()).into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        LineEnding::Lf
+    }
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use crate::interpreter::rule_defs::cmd_args::options::LineEnding;
+use crate::interpreter::rule_defs::cmd_args::options::QuoteStyle;
+use crate::interpreter::rule_defs::cmd_args::render_invalid_item_at;
+use crate::interpreter::rule_defs::cmd_args::CommandLineArgPath;
+
+#[test]
+fn windows_quote_leaves_simple_args_verbatim() {
+    let quote = |s| QuoteStyle::Windows.quote(s);
+    assert_eq!(quote("foo"), "foo");
+    assert_eq!(quote("--flag=value"), "--flag=value");
+    // Backslashes that do not precede a quote are not doubled.
+    assert_eq!(quote(r"C:\path\to\tool"), r"C:\path\to\tool");
+}
+
+#[test]
+fn windows_quote_wraps_args_needing_escaping() {
+    let quote = |s| QuoteStyle::Windows.quote(s);
+    assert_eq!(quote(""), r#""""#);
+    assert_eq!(quote("foo bar"), r#""foo bar""#);
+    assert_eq!(quote("foo\tbar"), "\"foo\tbar\"");
+    // A literal quote is escaped with a single backslash.
+    assert_eq!(quote(r#"a"b"#), r#""a\"b""#);
+    // Backslashes inside a quoted arg are left alone unless they precede a quote.
+    assert_eq!(quote(r"C:\path with space"), r#""C:\path with space""#);
+}
+
+#[test]
+fn windows_quote_doubles_backslashes_before_a_quote() {
+    // Two backslashes followed by a quote become four backslashes plus an escaped quote.
+    assert_eq!(QuoteStyle::Windows.quote(r#"a\\"b"#), r#""a\\\\\"b""#);
+    // A trailing backslash precedes the synthetic closing quote, so it is doubled.
+    assert_eq!(QuoteStyle::Windows.quote("a b\\"), r#""a b\\""#);
+}
+
+#[test]
+fn quote_style_parse_rejects_unknown() {
+    assert_eq!(QuoteStyle::parse("shell").unwrap(), QuoteStyle::Shell);
+    assert_eq!(QuoteStyle::parse("windows").unwrap(), QuoteStyle::Windows);
+    assert!(QuoteStyle::parse("powershell").is_err());
+}
+
+#[test]
+fn line_ending_parse_and_render() {
+    assert_eq!(LineEnding::parse("lf").unwrap().as_str(), "\n");
+    assert_eq!(LineEnding::parse("crlf").unwrap().as_str(), "\r\n");
+    assert_eq!(LineEnding::default().as_str(), "\n");
+    assert!(LineEnding::parse("cr").is_err());
+}
+
+#[test]
+fn command_line_arg_path_renders_index_stack() {
+    let mut path = CommandLineArgPath::default();
+    assert_eq!(path.render(), "args");
+    path.push(2);
+    path.push(0);
+    assert_eq!(path.render(), "args[2][0]");
+    path.pop();
+    assert_eq!(path.render(), "args[2]");
+}
+
+#[test]
+fn invalid_item_diagnostic_names_path_and_underlines_item() {
+    let out = render_invalid_item_at("args[2][0]", r#"["ok", 5]"#, "5");
+    assert!(out.contains("args[2][0]"), "{out}");
+    assert!(out.contains("not a valid command line item"), "{out}");
+    assert!(out.contains('^'), "{out}");
+}
+
+#[test]
+fn invalid_item_underline_is_char_aligned() {
+    // `café` is 4 chars but 5 bytes; the underline must be placed by character column, not byte
+    // offset, so the caret still lands under the `5`.
+    let out = render_invalid_item_at("args[1]", "[\"café\", 5]", "5");
+    let caret_line = out.lines().last().unwrap();
+    let leading_spaces = caret_line.len() - caret_line.trim_start().len();
+    // 2-space indent + 9 characters preceding the `5` in the container.
+    assert_eq!(leading_spaces, 11, "{out}");
+}
@@ -0,0 +1,140 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Debug;
+
+use buck2_core::fs::paths::RelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact::fs::ExecutorFs;
+use starlark::values::string::StarlarkStr;
+
+use crate::actions::artifact::Artifact;
+use crate::actions::artifact::OutputArtifact;
+
+/// The sink a [`CommandLineArgLike`] pushes its rendered arguments into.
+///
+/// Implemented for `Vec<String>` so that tests and callers that simply want the flattened command
+/// line do not need a bespoke collector.
+pub trait CommandLineBuilder {
+    /// Add an argument to the end of the command line.
+    fn push_arg(&mut self, s: String);
+}
+
+impl CommandLineBuilder for Vec<String> {
+    fn push_arg(&mut self, s: String) {
+        self.push(s)
+    }
+}
+
+/// A resolved on-disk location, carrying enough context to be rendered with the right path
+/// separator for the executor the command line is being built for.
+pub struct CommandLineLocation<'a> {
+    fs: &'a ExecutorFs<'a>,
+    relative_path: RelativePathBuf,
+}
+
+impl<'a> CommandLineLocation<'a> {
+    pub fn from_relative_path(relative_path: RelativePathBuf, fs: &'a ExecutorFs<'a>) -> Self {
+        Self { fs, relative_path }
+    }
+
+    /// Render the location as a string using the executor's path separator.
+    pub fn into_string(self) -> String {
+        let separator = self.fs.path_separator();
+        let mut res = String::with_capacity(self.relative_path.as_str().len());
+        for (i, part) in self.relative_path.as_str().split('/').enumerate() {
+            if i != 0 {
+                res.push_str(separator.as_str());
+            }
+            res.push_str(part);
+        }
+        res
+    }
+}
+
+/// Provides the path-resolution and scratch-file services a [`CommandLineArgLike`] needs while
+/// rendering itself, abstracted so that the same command line can be built for different executors.
+pub trait CommandLineContext {
+    /// The filesystem view of the executor the command line is being built for.
+    fn fs(&self) -> &ExecutorFs;
+
+    /// Resolve a project-relative path into an executor-relative location.
+    fn resolve_project_path(
+        &self,
+        path: ProjectRelativePathBuf,
+    ) -> anyhow::Result<CommandLineLocation>;
+
+    /// Resolve an artifact into the location it will occupy when the command runs.
+    fn resolve_artifact(&self, artifact: &Artifact) -> anyhow::Result<CommandLineLocation> {
+        self.resolve_project_path(self.fs().fs().resolve(artifact.get_path())?)
+    }
+
+    /// Allocate the path for the next scratch file a command-line item wants to materialize (e.g.
+    /// a response file). Implementations hand out a fresh path on each call.
+    fn next_macro_file_path(&mut self) -> anyhow::Result<RelativePathBuf>;
+}
+
+/// Collects the artifacts a command line reads and writes, so the action graph can track them as
+/// inputs and outputs.
+pub trait CommandLineArtifactVisitor {
+    fn visit_input(&mut self, input: Artifact);
+    fn visit_output(&mut self, artifact: OutputArtifact);
+}
+
+/// Visitor for `$(location ...)`-style write-to-file macros. Unused by most items.
+pub trait WriteToFileMacroVisitor {
+    fn visit_write_to_file_macro(&mut self, artifact: &Artifact) -> anyhow::Result<()>;
+}
+
+/// Anything that can render itself onto a command line: strings, artifacts, labels, `RunInfo`,
+/// `cmd_args`, and so on. This is the central abstraction that `ctx.actions.run` consumes.
+pub trait CommandLineArgLike {
+    /// Append this item's rendered arguments to `cli`, resolving any paths through `context`.
+    fn add_to_command_line(
+        &self,
+        cli: &mut dyn CommandLineBuilder,
+        context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()>;
+
+    /// Report the artifacts this item reads/writes to `visitor`.
+    fn visit_artifacts(&self, _visitor: &mut dyn CommandLineArtifactVisitor) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Whether this item contains an `attr`-derived value, which affects caching.
+    fn contains_arg_attr(&self) -> bool;
+
+    /// Report any write-to-file macros this item expands.
+    fn visit_write_to_file_macros(
+        &self,
+        _visitor: &mut dyn WriteToFileMacroVisitor,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// The frozen counterpart of [`CommandLineArgLike`], usable from the static, post-freeze world.
+pub trait FrozenCommandLineArgLike: CommandLineArgLike + Send + Sync + Debug + 'static {}
+
+impl<T> FrozenCommandLineArgLike for T where T: CommandLineArgLike + Send + Sync + Debug + 'static {}
+
+impl CommandLineArgLike for StarlarkStr {
+    fn add_to_command_line(
+        &self,
+        cli: &mut dyn CommandLineBuilder,
+        _context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()> {
+        cli.push_arg(self.as_str().to_owned());
+        Ok(())
+    }
+
+    fn contains_arg_attr(&self) -> bool {
+        false
+    }
+}
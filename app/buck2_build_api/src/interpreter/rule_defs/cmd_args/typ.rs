@@ -0,0 +1,447 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Display;
+
+use anyhow::Context as _;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use gazebo::prelude::*;
+use starlark::collections::StarlarkHasher;
+use starlark::environment::Methods;
+use starlark::environment::MethodsBuilder;
+use starlark::environment::MethodsStatic;
+use starlark::values::Freeze;
+use starlark::values::Freezer;
+use starlark::values::FrozenValue;
+use starlark::values::NoSerialize;
+use starlark::values::ProvidesStaticType;
+use starlark::values::StarlarkValue;
+use starlark::values::list::ListRef;
+use starlark::values::StringValue;
+use starlark::values::Trace;
+use starlark::values::Value;
+use starlark::values::ValueLike;
+
+use crate::interpreter::rule_defs::cmd_args::options::LineEnding;
+use crate::interpreter::rule_defs::cmd_args::options::QuoteStyle;
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineArgLike;
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineArtifactVisitor;
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineBuilder;
+use crate::interpreter::rule_defs::cmd_args::traits::CommandLineContext;
+use crate::interpreter::rule_defs::cmd_args::traits::WriteToFileMacroVisitor;
+use crate::interpreter::rule_defs::cmd_args::CommandLineArgPath;
+use crate::interpreter::rule_defs::cmd_args::ValueAsCommandLineLike;
+
+/// The per-`cmd_args` formatting options, shared between the mutable and frozen representations.
+/// The string-valued options are resolved to owned strings at construction so the same struct can
+/// back both.
+#[derive(Debug, Default, Clone)]
+pub struct CommandLineOptions {
+    pub(crate) delimiter: Option<String>,
+    pub(crate) format: Option<String>,
+    pub(crate) prepend: Option<String>,
+    pub(crate) quote: Option<QuoteStyle>,
+}
+
+impl CommandLineOptions {
+    fn is_empty(&self) -> bool {
+        self.delimiter.is_none()
+            && self.format.is_none()
+            && self.prepend.is_none()
+            && self.quote.is_none()
+    }
+
+    /// Render each wrapped item, apply `format`/`quote` per rendered argument, then join with
+    /// `delimiter` and/or `prepend` as documented on `cmd_args`.
+    fn add_args(
+        &self,
+        items: &[impl AsCommandLine],
+        cli: &mut dyn CommandLineBuilder,
+        context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()> {
+        let mut rendered = Vec::<String>::new();
+        for item in items {
+            let mut tmp = Vec::<String>::new();
+            item.as_command_line_arg()?
+                .add_to_command_line(&mut tmp, context)?;
+            for arg in tmp {
+                let formatted = match &self.format {
+                    Some(format) => format.replace("{}", &arg),
+                    None => arg,
+                };
+                rendered.push(match self.quote {
+                    Some(quote) => quote.quote(&formatted),
+                    None => formatted,
+                });
+            }
+        }
+
+        match &self.delimiter {
+            Some(delimiter) => {
+                if let Some(prepend) = &self.prepend {
+                    cli.push_arg(prepend.clone());
+                }
+                cli.push_arg(rendered.join(delimiter));
+            }
+            None => {
+                for arg in rendered {
+                    if let Some(prepend) = &self.prepend {
+                        cli.push_arg(prepend.clone());
+                    }
+                    cli.push_arg(arg);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Something that can be coerced to a `&dyn CommandLineArgLike`; lets [`CommandLineOptions`] work
+/// over both live `Value`s and `FrozenValue`s.
+pub trait AsCommandLine {
+    fn as_command_line_arg(&self) -> anyhow::Result<&dyn CommandLineArgLike>;
+}
+
+impl<'v> AsCommandLine for Value<'v> {
+    fn as_command_line_arg(&self) -> anyhow::Result<&dyn CommandLineArgLike> {
+        self.as_command_line_err()
+    }
+}
+
+/// The `cmd_args` Starlark value: a mutable collection of command-line items plus formatting
+/// options.
+#[derive(Debug, Default, Trace, ProvidesStaticType, NoSerialize)]
+pub struct StarlarkCommandLine<'v> {
+    items: RefCell<Vec<Value<'v>>>,
+    #[trace(unsafe_ignore)]
+    options: RefCell<CommandLineOptions>,
+    /// When set, the flattened command line is spilled into this response-file artifact and only
+    /// `@<path>` is emitted onto the actual command line.
+    at_argfile: Option<Value<'v>>,
+    #[trace(unsafe_ignore)]
+    line_ending: LineEnding,
+}
+
+/// The frozen counterpart of [`StarlarkCommandLine`].
+#[derive(Debug, ProvidesStaticType, NoSerialize)]
+pub struct FrozenStarlarkCommandLine {
+    items: Vec<FrozenValue>,
+    options: CommandLineOptions,
+    at_argfile: Option<FrozenValue>,
+    line_ending: LineEnding,
+}
+
+/// Recursively flatten `args`, expanding nested Starlark lists, into `out`. `path` tracks the index
+/// path into the nesting so that a coercion failure can report e.g. `args[2][0]` and underline the
+/// offending element within its surrounding list.
+fn coerce_args<'v>(
+    args: &[Value<'v>],
+    path: &mut CommandLineArgPath,
+    out: &mut Vec<Value<'v>>,
+) -> anyhow::Result<()> {
+    let container = render_container(args);
+    for (index, arg) in args.iter().enumerate() {
+        path.push(index);
+        match ListRef::from_value(*arg) {
+            Some(list) => coerce_args(list.content(), path, out)?,
+            None => {
+                arg.as_command_line_err_at(path, &container)?;
+                out.push(*arg);
+            }
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Render a Starlark-list-style repr of `args`, used as the container context in diagnostics.
+fn render_container(args: &[Value]) -> String {
+    let mut out = String::from("[");
+    for (i, arg) in args.iter().enumerate() {
+        if i != 0 {
+            out.push_str(", ");
+        }
+        out.push_str(&arg.to_repr());
+    }
+    out.push(']');
+    out
+}
+
+impl<'v> StarlarkCommandLine<'v> {
+    /// Construct a `cmd_args` from the positional `args` and keyword formatting options, as exposed
+    /// by the `cmd_args()` global.
+    pub fn try_from_values_with_options(
+        args: &[Value<'v>],
+        delimiter: Option<StringValue<'v>>,
+        format: Option<StringValue<'v>>,
+        prepend: Option<StringValue<'v>>,
+        quote: Option<QuoteStyle>,
+        at_argfile: Option<Value<'v>>,
+        line_ending: Option<LineEnding>,
+    ) -> anyhow::Result<StarlarkCommandLine<'v>> {
+        // Flatten nested lists into a single sequence of leaf items, eagerly validating that each
+        // coerces to a command-line argument so that construction — rather than action execution —
+        // surfaces the error, and with enough positional context to pinpoint the offending element.
+        let mut items = Vec::with_capacity(args.len());
+        coerce_args(args, &mut CommandLineArgPath::default(), &mut items)?;
+
+        Ok(StarlarkCommandLine {
+            items: RefCell::new(items),
+            options: RefCell::new(CommandLineOptions {
+                delimiter: delimiter.map(|s| s.as_str().to_owned()),
+                format: format.map(|s| s.as_str().to_owned()),
+                prepend: prepend.map(|s| s.as_str().to_owned()),
+                quote,
+            }),
+            at_argfile,
+            line_ending: line_ending.unwrap_or_default(),
+        })
+    }
+
+    /// Add further items to the command line; backs the `cmd.add` method.
+    pub fn add_value(&self, value: Value<'v>) -> anyhow::Result<()> {
+        value.as_command_line_err()?;
+        self.items.borrow_mut().push(value);
+        Ok(())
+    }
+}
+
+impl<'v> CommandLineArgLike for StarlarkCommandLine<'v> {
+    fn add_to_command_line(
+        &self,
+        cli: &mut dyn CommandLineBuilder,
+        context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()> {
+        let options = self.options.borrow();
+        let items = self.items.borrow();
+        match &self.at_argfile {
+            None => options.add_args(&items, cli, context),
+            Some(output) => {
+                let mut args = Vec::<String>::new();
+                options.add_args(&items, &mut args, context)?;
+                CommandLineArgFile {
+                    output: output.as_command_line_err()?,
+                    args,
+                    line_ending: self.line_ending,
+                }
+                .add_to_command_line(cli, context)
+            }
+        }
+    }
+
+    fn visit_artifacts(&self, visitor: &mut dyn CommandLineArtifactVisitor) -> anyhow::Result<()> {
+        for item in self.items.borrow().iter() {
+            item.as_command_line_err()?.visit_artifacts(visitor)?;
+        }
+        if let Some(output) = &self.at_argfile {
+            output.as_command_line_err()?.visit_artifacts(visitor)?;
+        }
+        Ok(())
+    }
+
+    fn contains_arg_attr(&self) -> bool {
+        self.items
+            .borrow()
+            .iter()
+            .any(|i| i.as_command_line().map_or(false, |a| a.contains_arg_attr()))
+    }
+
+    fn visit_write_to_file_macros(
+        &self,
+        visitor: &mut dyn WriteToFileMacroVisitor,
+    ) -> anyhow::Result<()> {
+        for item in self.items.borrow().iter() {
+            item.as_command_line_err()?
+                .visit_write_to_file_macros(visitor)?;
+        }
+        Ok(())
+    }
+}
+
+impl CommandLineArgLike for FrozenStarlarkCommandLine {
+    fn add_to_command_line(
+        &self,
+        cli: &mut dyn CommandLineBuilder,
+        context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()> {
+        match &self.at_argfile {
+            None => self.options.add_args(&self.items, cli, context),
+            Some(output) => {
+                let mut args = Vec::<String>::new();
+                self.options.add_args(&self.items, &mut args, context)?;
+                CommandLineArgFile {
+                    output: output.to_value().as_command_line_err()?,
+                    args,
+                    line_ending: self.line_ending,
+                }
+                .add_to_command_line(cli, context)
+            }
+        }
+    }
+
+    fn visit_artifacts(&self, visitor: &mut dyn CommandLineArtifactVisitor) -> anyhow::Result<()> {
+        for item in self.items.iter() {
+            item.to_value().as_command_line_err()?.visit_artifacts(visitor)?;
+        }
+        if let Some(output) = &self.at_argfile {
+            output
+                .to_value()
+                .as_command_line_err()?
+                .visit_artifacts(visitor)?;
+        }
+        Ok(())
+    }
+
+    fn contains_arg_attr(&self) -> bool {
+        self.items.iter().any(|i| {
+            i.to_value()
+                .as_command_line()
+                .map_or(false, |a| a.contains_arg_attr())
+        })
+    }
+}
+
+impl AsCommandLine for FrozenValue {
+    fn as_command_line_arg(&self) -> anyhow::Result<&dyn CommandLineArgLike> {
+        self.to_value().as_command_line_err()
+    }
+}
+
+impl<'v> Freeze for StarlarkCommandLine<'v> {
+    type Frozen = FrozenStarlarkCommandLine;
+
+    fn freeze(self, freezer: &Freezer) -> anyhow::Result<Self::Frozen> {
+        let items = self
+            .items
+            .into_inner()
+            .into_try_map(|v| v.freeze(freezer))?;
+        let at_argfile = self.at_argfile.try_map(|v| v.freeze(freezer))?;
+        Ok(FrozenStarlarkCommandLine {
+            items,
+            options: self.options.into_inner(),
+            at_argfile,
+            line_ending: self.line_ending,
+        })
+    }
+}
+
+impl<'v> Display for StarlarkCommandLine<'v> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cmd_args(")?;
+        for (i, item) in self.items.borrow().iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for FrozenStarlarkCommandLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cmd_args(")?;
+        for (i, item) in self.items.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[starlark_value(type = "cmd_args")]
+impl<'v> StarlarkValue<'v> for StarlarkCommandLine<'v> {
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(command_line_methods)
+    }
+
+    fn write_hash(&self, _hasher: &mut StarlarkHasher) -> anyhow::Result<()> {
+        // `cmd_args` is mutable, so it is not hashable.
+        Err(starlark::values::ValueError::Unhashable.into())
+    }
+}
+
+#[starlark_value(type = "cmd_args")]
+impl<'v> StarlarkValue<'v> for FrozenStarlarkCommandLine {
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(command_line_methods)
+    }
+}
+
+/// A [`CommandLineArgLike`] that spills the command line it wraps into a response file and emits
+/// only `@<path>`, mirroring the `@path` convention understood by rustc and many other compilers.
+/// The wrapped arguments are written to `output`, one per line, using `line_ending`.
+struct CommandLineArgFile<'a> {
+    output: &'a dyn CommandLineArgLike,
+    args: Vec<String>,
+    line_ending: LineEnding,
+}
+
+impl<'a> CommandLineArgLike for CommandLineArgFile<'a> {
+    fn add_to_command_line(
+        &self,
+        cli: &mut dyn CommandLineBuilder,
+        context: &mut dyn CommandLineContext,
+    ) -> anyhow::Result<()> {
+        // Resolve the response file's executor-relative path.
+        let mut path = Vec::<String>::new();
+        self.output.add_to_command_line(&mut path, context)?;
+        let [relative_path] = <[String; 1]>::try_from(path).map_err(|_| {
+            anyhow::anyhow!("`at_argfile` output must resolve to exactly one path")
+        })?;
+
+        // Write the flattened arguments to the response file so the tool can read them via `@path`.
+        let abs_path = context
+            .fs()
+            .fs()
+            .fs()
+            .resolve(ProjectRelativePath::new(relative_path.as_str())?);
+        let mut contents = self.args.join(self.line_ending.as_str());
+        if !contents.is_empty() {
+            contents.push_str(self.line_ending.as_str());
+        }
+        std::fs::write(abs_path.as_path(), contents)
+            .with_context(|| format!("writing response file `{relative_path}`"))?;
+
+        cli.push_arg(format!("@{relative_path}"));
+        Ok(())
+    }
+
+    fn visit_artifacts(&self, visitor: &mut dyn CommandLineArtifactVisitor) -> anyhow::Result<()> {
+        // The response file is produced by this command, so register it like any other output.
+        self.output.visit_artifacts(visitor)
+    }
+
+    fn contains_arg_attr(&self) -> bool {
+        false
+    }
+}
+
+#[starlark_module]
+fn command_line_methods(builder: &mut MethodsBuilder) {
+    /// Add one or more items to the command line, returning the `cmd_args` for chaining.
+    fn add<'v>(
+        this: Value<'v>,
+        #[starlark(args)] args: Vec<Value<'v>>,
+    ) -> anyhow::Result<Value<'v>> {
+        let cli = this
+            .downcast_ref::<StarlarkCommandLine>()
+            .ok_or_else(|| anyhow::anyhow!("`add` can only be called on a mutable `cmd_args`"))?;
+        for arg in args {
+            cli.add_value(arg)?;
+        }
+        Ok(this)
+    }
+}